@@ -1,24 +1,214 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
-use std::io;
-use std::fs::File;
+use sha2::Sha256;
+
+// these live under src/lib/ rather than alongside this file, so each needs
+// an explicit path instead of the usual src/<name>.rs / src/<name>/mod.rs lookup
+#[path = "lib/cache.rs"]
+pub mod cache;
+#[path = "lib/git_source.rs"]
+pub mod git_source;
+#[path = "lib/manifest.rs"]
+pub mod manifest;
+#[path = "lib/pkg.rs"]
+pub mod pkg;
+#[path = "lib/setup.rs"]
+pub mod setup;
+
+// raised back into Python for any failure in the embedded `dash` API (a
+// failed download, a non-UTF-8 command output, a missing binary, ...)
+// instead of aborting the whole interpreter
+create_exception!(wix_py, DashError, PyException);
+
+// result of `cmd`, exposing stdout/stderr separately plus the exit status so
+// a package install script can tell a failed compilation step apart from a
+// quiet one and read compiler diagnostics from stderr
+#[pyclass]
+pub struct CmdOutput {
+    #[pyo3(get)]
+    pub stdout: String,
+    #[pyo3(get)]
+    pub stderr: String,
+    #[pyo3(get)]
+    pub status: i32,
+}
 
 #[pyfunction]
-pub fn cmd(cmd: String, args: Vec<String>) -> PyResult<String> {
-    let child = std::process::Command::new(cmd)
-        .args(args)
+#[args(cwd = "None", env = "None", check = "false")]
+pub fn cmd(
+    cmd: String,
+    args: Vec<String>,
+    cwd: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
+    check: bool,
+) -> PyResult<CmdOutput> {
+    let mut command = std::process::Command::new(&cmd);
+    command
+        .args(&args)
         .stdout(std::process::Stdio::piped())
-        .spawn()?;
+        .stderr(std::process::Stdio::piped());
+
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+
+    if let Some(env) = env {
+        for (key, value) in env {
+            command.env(key, value);
+        }
+    }
+
+    let output = command
+        .output()
+        .map_err(|e| DashError::new_err(format!("failed to run {}: {}", cmd, e)))?;
 
-    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let status = output.status.code().unwrap_or(-1);
 
-    Ok(String::from_utf8(output.stdout).unwrap())
+    if check && !output.status.success() {
+        return Err(DashError::new_err(format!(
+            "{} exited with status {}: {}",
+            cmd, status, stderr
+        )));
+    }
+
+    Ok(CmdOutput { stdout, stderr, status })
 }
 
+// download `url` into `file`, verifying an expected digest if one is given,
+// resuming via an HTTP Range request when a partial file already exists, and
+// reporting progress through an optional `(downloaded, total)` callback.
+// returns the number of bytes written (as before), but only once any
+// requested digest has been verified to match.
 #[pyfunction]
-pub fn get(_py: Python, url: String, file: String) -> u64{
-    let mut resp = reqwest::blocking::get(url).expect("Failed to get");
-    let mut out = File::create(file).expect("failed to create file");
-    io::copy(&mut resp, &mut out).expect("failed to copy")
+#[args(sha256 = "None", blake3 = "None", resume = "true", progress = "None")]
+pub fn get(
+    py: Python,
+    url: String,
+    file: String,
+    sha256: Option<String>,
+    blake3: Option<String>,
+    resume: bool,
+    progress: Option<PyObject>,
+) -> PyResult<u64> {
+    use std::io::{Read, Seek, Write};
+
+    let path = std::path::Path::new(&file);
+    let existing_len = if resume && path.exists() {
+        path.metadata()
+            .map_err(|e| DashError::new_err(format!("failed to stat {}: {}", file, e)))?
+            .len()
+    } else {
+        0
+    };
+
+    let client = reqwest::blocking::Client::new();
+    let mut request = client.get(&url);
+    if existing_len > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing_len));
+    }
+
+    let mut resp = request
+        .send()
+        .map_err(|e| DashError::new_err(format!("failed to get {}: {}", url, e)))?;
+
+    let resumed = existing_len > 0 && resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let body_len = resp.content_length();
+    let total = body_len.map(|b| if resumed { existing_len + b } else { b });
+
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(!resumed)
+        .open(&file)
+        .map_err(|e| DashError::new_err(format!("failed to open {}: {}", file, e)))?;
+
+    if resumed {
+        out.seek(std::io::SeekFrom::End(0))
+            .map_err(|e| DashError::new_err(format!("failed to seek {}: {}", file, e)))?;
+    }
+
+    let mut sha256_hasher = sha256.as_ref().map(|_| Sha256::new());
+    let mut blake3_hasher = blake3.as_ref().map(|_| blake3::Hasher::new());
+
+    // a resumed download only streams the missing tail - if a digest is
+    // expected, hash the bytes already on disk first so the final digest is
+    // computed over the whole file, not just the newly-appended part
+    if resumed && (sha256_hasher.is_some() || blake3_hasher.is_some()) {
+        let mut existing = std::fs::File::open(&file)
+            .map_err(|e| DashError::new_err(format!("failed to reread {}: {}", file, e)))?;
+        let mut pre_buf = [0u8; 64 * 1024];
+
+        loop {
+            let n = existing
+                .read(&mut pre_buf)
+                .map_err(|e| DashError::new_err(format!("failed to reread {}: {}", file, e)))?;
+            if n == 0 {
+                break;
+            }
+
+            if let Some(hasher) = sha256_hasher.as_mut() {
+                sha2::Digest::update(hasher, &pre_buf[..n]);
+            }
+            if let Some(hasher) = blake3_hasher.as_mut() {
+                hasher.update(&pre_buf[..n]);
+            }
+        }
+    }
+
+    let mut downloaded = if resumed { existing_len } else { 0 };
+    let mut written = 0u64;
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let n = resp
+            .read(&mut buf)
+            .map_err(|e| DashError::new_err(format!("failed to read response: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+
+        out.write_all(&buf[..n])
+            .map_err(|e| DashError::new_err(format!("failed to write {}: {}", file, e)))?;
+
+        if let Some(hasher) = sha256_hasher.as_mut() {
+            sha2::Digest::update(hasher, &buf[..n]);
+        }
+        if let Some(hasher) = blake3_hasher.as_mut() {
+            hasher.update(&buf[..n]);
+        }
+
+        downloaded += n as u64;
+        written += n as u64;
+
+        if let Some(cb) = &progress {
+            cb.call1(py, (downloaded, total))?;
+        }
+    }
+
+    if let (Some(expected), Some(hasher)) = (sha256, sha256_hasher) {
+        let actual = format!("{:x}", hasher.finalize());
+        if actual != expected {
+            return Err(DashError::new_err(format!(
+                "sha256 mismatch for {}: expected {}, got {}",
+                file, expected, actual
+            )));
+        }
+    }
+
+    if let (Some(expected), Some(hasher)) = (blake3, blake3_hasher) {
+        let actual = hasher.finalize().to_hex().to_string();
+        if actual != expected {
+            return Err(DashError::new_err(format!(
+                "blake3 mismatch for {}: expected {}, got {}",
+                file, expected, actual
+            )));
+        }
+    }
+
+    Ok(written)
 }
 
 #[pyfunction]
@@ -26,20 +216,107 @@ pub fn hello() {
     println!("Hello, Python!");
 }
 
+// normalized host os, matching the naming manifest::target_triple expects
+#[pyfunction]
+pub fn platform() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    }
+}
+
+// normalized host arch, matching the naming manifest::target_triple expects
+#[pyfunction]
+pub fn arch() -> &'static str {
+    if cfg!(target_arch = "aarch64") {
+        "aarch64"
+    } else {
+        "x86_64"
+    }
+}
+
+// read an environment variable, falling back to `default` when unset
+#[pyfunction]
+pub fn env(name: String, default: Option<String>) -> Option<String> {
+    std::env::var(name).ok().or(default)
+}
+
+// ~/wix/cache, where downloaded package contents are kept
+#[pyfunction]
+pub fn cache_dir() -> PyResult<String> {
+    wix_dir("cache")
+}
+
+// ~/wix, the manager's install prefix
+#[pyfunction]
+pub fn prefix() -> PyResult<String> {
+    wix_dir("")
+}
+
+fn wix_dir(sub: &str) -> PyResult<String> {
+    let home = dirs::home_dir().ok_or_else(|| DashError::new_err("failed to get home dir"))?;
+    let path = if sub.is_empty() {
+        home.join("wix")
+    } else {
+        home.join("wix").join(sub)
+    };
+
+    path.to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| DashError::new_err("wix path is not valid utf-8"))
+}
+
 #[pymodule]
-pub fn wix_py(_py: Python, m: &PyModule) -> PyResult<()> {
+pub fn wix_py(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("DashError", py.get_type::<DashError>())?;
+
     m.add_function(wrap_pyfunction!(cmd, m)?)?;
     m.add_function(wrap_pyfunction!(get, m)?)?;
     m.add_function(wrap_pyfunction!(hello, m)?)?;
+    m.add_function(wrap_pyfunction!(platform, m)?)?;
+    m.add_function(wrap_pyfunction!(arch, m)?)?;
+    m.add_function(wrap_pyfunction!(env, m)?)?;
+    m.add_function(wrap_pyfunction!(cache_dir, m)?)?;
+    m.add_function(wrap_pyfunction!(prefix, m)?)?;
 
     Ok(())
 }
 
-// get variable from python
-pub fn exec_py(py: Python, code: String, file: String, name: String) -> String {
-    Python::with_gil(|py| -> String {
-        let py_mod = PyModule::from_code(py, &code, &file, &name).unwrap();
-        let py_var = py_mod.getattr("version").unwrap();
-        py_var.extract::<String>().unwrap()
-    })
+// load `code` as a module and pull a named attribute out of it. if the
+// attribute is callable it is invoked with `args` first, otherwise its value
+// is extracted directly - this lets a package manifest expose a plain
+// `version` string, or functions like `install()`/`dependencies()`, through
+// one uniform entry point.
+pub fn call_py<'a, T>(
+    py: Python<'a>,
+    code: &str,
+    file: &str,
+    module_name: &str,
+    func_name: &str,
+    args: Vec<String>,
+) -> PyResult<T>
+where
+    T: pyo3::FromPyObject<'a>,
+{
+    let py_mod = PyModule::from_code(py, code, file, module_name)
+        .map_err(|e| DashError::new_err(format!("failed to load {}: {}", module_name, e)))?;
+
+    let attr = py_mod
+        .getattr(func_name)
+        .map_err(|e| DashError::new_err(format!("{} has no attribute {}: {}", module_name, func_name, e)))?;
+
+    let value: &PyAny = if attr.is_callable() {
+        let py_args = pyo3::types::PyTuple::new(py, args.into_iter().map(|a| a.into_py(py)));
+        attr.call1(py_args)
+            .map_err(|e| DashError::new_err(format!("{} raised: {}", func_name, e)))?
+    } else {
+        attr
+    };
+
+    value
+        .extract::<T>()
+        .map_err(|e| DashError::new_err(format!("failed to extract {} result: {}", func_name, e)))
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+// clone (or reuse) a package repo under wix/cache/git/<name>, derived from
+// the repo url so the same source is never cloned twice, then check out the
+// commit pinned in the package's ManifestPackage for reproducibility
+pub fn clone_or_checkout(name: &str, url: &str, commit: &str) -> Result<PathBuf, String> {
+    // e.g. "https://github.com/owner/repo.git" -> "github_com_owner_repo"
+    let dir_name: String = url
+        .split("://")
+        .last()
+        .unwrap_or(url)
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+
+    let dest = dirs::home_dir()
+        .expect("failed to get home dir")
+        .join("wix/cache/git")
+        .join(&dir_name);
+
+    let repo = if dest.exists() {
+        git2::Repository::open(&dest).map_err(|e| format!("failed to open {}: {}", name, e))?
+    } else {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create cache dir for {}: {}", name, e))?;
+        }
+
+        git2::Repository::clone(url, &dest)
+            .map_err(|e| format!("failed to clone {}: {}", url, e))?
+    };
+
+    let oid = git2::Oid::from_str(commit)
+        .map_err(|e| format!("invalid commit {} for {}: {}", commit, name, e))?;
+
+    let commit_obj = repo
+        .find_commit(oid)
+        .map_err(|e| format!("commit {} not found in {}: {}", commit, name, e))?;
+
+    repo.checkout_tree(commit_obj.as_object(), None)
+        .map_err(|e| format!("failed to checkout {} in {}: {}", commit, name, e))?;
+    repo.set_head_detached(oid)
+        .map_err(|e| format!("failed to set HEAD in {}: {}", name, e))?;
+
+    Ok(dest)
+}
+
+pub struct ClonedRepo {
+    pub name: String,
+    pub path: PathBuf,
+    pub head: String,
+}
+
+// list every package repo previously cloned into wix/cache/git/
+pub fn list_cloned() -> Result<Vec<ClonedRepo>, String> {
+    let git_dir = dirs::home_dir()
+        .expect("failed to get home dir")
+        .join("wix/cache/git");
+
+    if !git_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut repos = Vec::new();
+
+    let entries = std::fs::read_dir(&git_dir).map_err(|e| e.to_string())?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let repo = match git2::Repository::open(&path) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .map(|c| c.id().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        repos.push(ClonedRepo { name, path, head });
+    }
+
+    Ok(repos)
+}
+
+pub struct UpdateResult {
+    pub name: String,
+    pub advanced: bool,
+    pub from: String,
+    pub to: String,
+}
+
+// fast-forward (or rebase if the local HEAD has diverged) each cloned repo
+// against its remote default branch
+pub fn update_all() -> Result<Vec<UpdateResult>, String> {
+    let mut results = Vec::new();
+
+    for cloned in list_cloned()? {
+        let repo = git2::Repository::open(&cloned.path)
+            .map_err(|e| format!("failed to open {}: {}", cloned.name, e))?;
+
+        let mut remote = repo
+            .find_remote("origin")
+            .map_err(|e| format!("no origin remote for {}: {}", cloned.name, e))?;
+
+        remote
+            .fetch(&[] as &[&str], None, None)
+            .map_err(|e| format!("failed to fetch {}: {}", cloned.name, e))?;
+
+        let fetch_head = repo
+            .find_reference("FETCH_HEAD")
+            .and_then(|r| r.peel_to_commit())
+            .map_err(|e| format!("no FETCH_HEAD for {}: {}", cloned.name, e))?;
+
+        let before = cloned.head.clone();
+        let after = fetch_head.id().to_string();
+
+        if before != after {
+            let analysis = repo
+                .merge_analysis(&[&repo
+                    .find_annotated_commit(fetch_head.id())
+                    .map_err(|e| e.to_string())?])
+                .map_err(|e| e.to_string())?;
+
+            if analysis.0.is_fast_forward() {
+                let mut head_ref = repo
+                    .head()
+                    .map_err(|e| format!("failed to get HEAD for {}: {}", cloned.name, e))?;
+                head_ref
+                    .set_target(fetch_head.id(), "wix: fast-forward update")
+                    .map_err(|e| e.to_string())?;
+                repo.checkout_head(None).map_err(|e| e.to_string())?;
+            } else if analysis.0.is_normal() {
+                // diverged history: rebase local commits on top of upstream
+                let mut rebase = repo
+                    .rebase(
+                        None,
+                        Some(
+                            &repo
+                                .find_annotated_commit(fetch_head.id())
+                                .map_err(|e| e.to_string())?,
+                        ),
+                        None,
+                        None,
+                    )
+                    .map_err(|e| format!("failed to start rebase for {}: {}", cloned.name, e))?;
+
+                while let Some(op) = rebase.next() {
+                    op.map_err(|e| format!("rebase step failed for {}: {}", cloned.name, e))?;
+                    rebase
+                        .commit(None, &repo.signature().map_err(|e| e.to_string())?, None)
+                        .map_err(|e| format!("rebase commit failed for {}: {}", cloned.name, e))?;
+                }
+
+                rebase.finish(None).map_err(|e| e.to_string())?;
+            }
+        }
+
+        results.push(UpdateResult {
+            name: cloned.name,
+            advanced: before != after,
+            from: before,
+            to: after,
+        });
+    }
+
+    Ok(results)
+}
+
@@ -1,12 +1,21 @@
 use std::collections::HashMap;
 
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use mlua::{DeserializeOptions, Lua, LuaSerdeExt, Table};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
 use smart_default::SmartDefault;
 
 use crate as krait;
 
+// base64-encoded digest prefixed with the hash function name, e.g. "sha256-<base64>"
+// see https://www.w3.org/TR/SRI/#the-integrity-attribute
+pub fn sri_string(algo: &str, digest: &[u8]) -> String {
+    format!("{}-{}", algo, base64::encode(digest))
+}
+
 #[derive(SmartDefault, Deserialize, Serialize, Debug, Clone)]
 pub struct Manifest {
     pub repo: String,
@@ -18,9 +27,36 @@ pub struct Manifest {
 
 #[derive(SmartDefault, Deserialize, Serialize, Debug, Clone)]
 pub struct ManifestPackage {
+    // rust-style target triple this entry was built for, e.g.
+    // "x86_64-unknown-linux-gnu", or "any" for noarch content
+    #[default = "any"]
+    pub target: String,
+
     pub commit: String,
     pub path: String,
     pub contents: Vec<ManifestPackageContent>,
+
+    // when set, this package is sourced directly from a git repository
+    // (cloned into wix/cache/git/<name> and checked out to `commit`) rather
+    // than flattened into raw.githubusercontent urls in `contents`
+    pub git_url: String,
+}
+
+// known (os, arch) -> target triple mappings, matching setup::get_os()/get_arch()
+const TARGET_TRIPLES: &[(&str, &str, &str)] = &[
+    ("linux", "x86_64", "x86_64-unknown-linux-gnu"),
+    ("linux", "aarch64", "aarch64-unknown-linux-gnu"),
+    ("macos", "x86_64", "x86_64-apple-darwin"),
+    ("macos", "aarch64", "aarch64-apple-darwin"),
+    ("windows", "x86_64", "x86_64-pc-windows-msvc"),
+    ("windows", "aarch64", "aarch64-pc-windows-msvc"),
+];
+
+pub fn target_triple(os: &str, arch: &str) -> Option<&'static str> {
+    TARGET_TRIPLES
+        .iter()
+        .find(|(o, a, _)| *o == os && *a == arch)
+        .map(|(_, _, t)| *t)
 }
 
 #[derive(SmartDefault, Deserialize, Serialize, Debug, Clone)]
@@ -30,10 +66,186 @@ pub struct ManifestPackageContent {
 
     // used for consistency sake because git still uses sha1
     pub sha1: String,
+
+    // authoritative integrity digest used to verify downloads; sha1 above is
+    // kept only for the git-blob correspondence, never for verification
+    pub sha256: String,
+
+    // sha256 encoded as a Subresource-Integrity string ("sha256-<base64>")
+    pub integrity: String,
+
     pub url: String,
 }
 
+impl ManifestPackageContent {
+    // verify `bytes` (e.g. a freshly downloaded file) against this entry's
+    // recorded integrity, failing closed on any mismatch
+    pub fn verify_integrity(&self, bytes: &[u8]) -> Result<(), String> {
+        let mut hasher = Sha256::new();
+        hasher.update(bytes);
+        let actual_digest = hasher.finalize();
+        let actual = sri_string("sha256", &actual_digest);
+
+        if actual != self.integrity {
+            return Err(format!(
+                "integrity mismatch for {}: expected {}, got {}",
+                self.path, self.integrity, actual
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct VerifyReport {
+    pub matches: Vec<String>,
+    pub mismatches: Vec<(String, String, String)>, // (path, expected, actual)
+    pub missing: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty() && self.missing.is_empty()
+    }
+}
+
 impl Manifest {
+    // fetch and parse the manifest.lua for `repo` on `branch`
+    pub async fn fetch(repo: &str, branch: &str) -> Result<Manifest, String> {
+        let url = format!(
+            "https://raw.githubusercontent.com/{}/{}/manifest.lua",
+            repo, branch
+        );
+
+        let resp = reqwest::get(&url)
+            .await
+            .map_err(|e| format!("failed to fetch manifest: {}", e))?;
+
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| format!("failed to read manifest: {}", e))?;
+
+        // a compromised raw.githubusercontent host shouldn't be able to
+        // silently swap manifest contents: verify the detached signature
+        // against the operator's trusted key before trusting any URL in it
+        if let Ok(key_path) = std::env::var("WIX_TRUSTED_KEY") {
+            let sig_url = format!(
+                "https://raw.githubusercontent.com/{}/{}/manifest.lua.sig",
+                repo, branch
+            );
+
+            let sig_bytes = reqwest::get(&sig_url)
+                .await
+                .map_err(|e| format!("failed to fetch manifest signature: {}", e))?
+                .bytes()
+                .await
+                .map_err(|e| format!("failed to read manifest signature: {}", e))?;
+
+            let signature = Signature::from_bytes(&sig_bytes)
+                .map_err(|e| format!("invalid manifest signature: {}", e))?;
+
+            let key_bytes = std::fs::read(&key_path)
+                .map_err(|e| format!("failed to read trusted key {}: {}", key_path, e))?;
+            let public_key = PublicKey::from_bytes(&key_bytes)
+                .map_err(|e| format!("invalid trusted key {}: {}", key_path, e))?;
+
+            Manifest::verify_signature(&text, &signature, &public_key)?;
+        }
+
+        Ok(Manifest::parse(text))
+    }
+
+    // re-hash every ManifestPackageContent under `cache_root` and report
+    // matches, hash mismatches, and missing files. when `list_missing_only`
+    // is set, skip hashing entirely and only report absent files (useful for
+    // CI prefetch checks).
+    //
+    // `os`/`arch` identify the running host so this resolves against the
+    // same `~/wix/cache/<name>/<os>-<arch>/<version>.py` layout install
+    // actually uses (see main.rs), rather than the generator-time
+    // `packages/<name>/<target>/...` paths recorded in `content.path`;
+    // packages whose target doesn't match the host (and isn't "any") are
+    // skipped entirely since they were never installed here.
+    pub fn verify(&self, cache_root: &std::path::Path, os: &str, arch: &str, list_missing_only: bool) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        let wanted = target_triple(os, arch);
+
+        for (name, versions) in &self.packages {
+            for (version, packages) in versions {
+                for package in packages {
+                    if Some(package.target.as_str()) != wanted && package.target != "any" {
+                        continue;
+                    }
+
+                    // installing only ever writes the one content entry whose
+                    // name matches "<version>.py" to cache; other contents
+                    // (if any) never land in the install layout at all
+                    let content = match package
+                        .contents
+                        .iter()
+                        .find(|c| c.name == format!("{}.py", version))
+                    {
+                        Some(c) => c,
+                        None => continue,
+                    };
+
+                    let file_path = cache_root
+                        .join(name)
+                        .join(format!("{}-{}", os, arch))
+                        .join(format!("{}.py", version));
+                    let display_path = format!("{}/{}-{}/{}.py", name, os, arch, version);
+
+                    if !file_path.exists() {
+                        report.missing.push(display_path);
+                        continue;
+                    }
+
+                    if list_missing_only {
+                        continue;
+                    }
+
+                    let bytes = match std::fs::read(&file_path) {
+                        Ok(b) => b,
+                        Err(_) => {
+                            report.missing.push(display_path);
+                            continue;
+                        }
+                    };
+
+                    match content.verify_integrity(&bytes) {
+                        Ok(()) => report.matches.push(display_path),
+                        Err(_) => report.mismatches.push((
+                            display_path,
+                            content.integrity.clone(),
+                            sri_string("sha256", &{
+                                let mut hasher = Sha256::new();
+                                hasher.update(&bytes);
+                                hasher.finalize()
+                            }),
+                        )),
+                    }
+                }
+            }
+        }
+
+        report
+    }
+
+    // pick the ManifestPackage entry matching the running (os, arch), falling
+    // back to an "any"/noarch entry when no target-specific build exists
+    pub fn select_package(&self, name: &str, version: &str, os: &str, arch: &str) -> Option<&ManifestPackage> {
+        let candidates = self.packages.get(name)?.get(version)?;
+
+        let wanted = target_triple(os, arch);
+
+        candidates
+            .iter()
+            .find(|p| Some(p.target.as_str()) == wanted)
+            .or_else(|| candidates.iter().find(|p| p.target == "any"))
+    }
+
     pub fn parse(s: String) -> Self {
         let lua = Lua::new();
         let globals = lua.globals();
@@ -214,6 +426,14 @@ impl Manifest {
             }
         }
 
+        // snapshot of the manifest as parsed from disk, used to skip
+        // rehashing packages whose commit hasn't advanced since last run
+        let previous_packages = manifest.packages.clone();
+
+        // keep package directories in a stable order so the emitted manifest
+        // doesn't reorder itself on every run when nothing changed
+        package_dirs.sort();
+
         for package_dir in package_dirs {
             let package_name = package_dir
                 .file_name()
@@ -268,129 +488,300 @@ impl Manifest {
 
             let package_commit = package_commit.id().to_string();
 
+            // get the version from the package manifest
+            let version = package_manifest.ver;
+
+            // packages already recorded for this name/version last run; if a
+            // target's commit hasn't advanced we reuse its stored contents
+            // instead of rehashing every file again
+            let previous_targets: HashMap<String, &ManifestPackage> = previous_packages
+                .get(&package_name)
+                .and_then(|v| v.get(&version))
+                .map(|list| list.iter().map(|p| (p.target.clone(), p)).collect())
+                .unwrap_or_default();
+
             // package path relative to the repo root
             let package_path = format!("packages/{}", package_name);
 
-            // check for contents of the package
-            let package_contents = match std::fs::read_dir(&package_dir) {
-                Ok(c) => c,
+            // a package either ships one noarch payload (files directly under
+            // packages/<name>/) or a host/target matrix (one subdirectory per
+            // target triple, e.g. packages/<name>/x86_64-unknown-linux-gnu/)
+            let mut unrecognized_targets: Vec<String> = Vec::new();
+
+            let mut target_dirs: Vec<(String, std::path::PathBuf)> = match std::fs::read_dir(&package_dir) {
+                Ok(entries) => entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| {
+                        let name = e.file_name().to_str()?.to_string();
+                        if is_target_name(&name) {
+                            Some((name, e.path()))
+                        } else {
+                            unrecognized_targets.push(name);
+                            None
+                        }
+                    })
+                    .collect(),
                 Err(e) => {
                     eprintln!("Error reading package contents: {}", e);
                     krait::exit!(1);
                 }
             };
 
-            let mut contents: Vec<ManifestPackageContent> = Vec::new();
+            // a subdirectory that isn't a known target triple (or "any") is
+            // almost always a typo'd target name, not an intentional noarch
+            // layout; silently dropping it would ship a manifest that's
+            // quietly missing that platform's payload
+            if !unrecognized_targets.is_empty() {
+                eprintln!(
+                    "Error: Package {} has unrecognized target director{}: {}",
+                    package_name,
+                    if unrecognized_targets.len() == 1 { "y" } else { "ies" },
+                    unrecognized_targets.join(", ")
+                );
+                eprintln!("Expected a known target triple or \"any\"");
+                krait::exit!(1);
+            }
 
-            for content in package_contents {
-                if let Ok(content) = content {
-                    let content_path = content.path();
+            target_dirs.sort();
 
-                    if content_path.is_dir() {
-                        eprintln!("Error: Package {} contains a directory", package_name);
-                        eprintln!("Directories are not currently supported");
-                        krait::exit!(1);
+            let build_package = |target: String, dir: &std::path::Path, path: String| -> ManifestPackage {
+                if let Some(previous) = previous_targets.get(&target) {
+                    if previous.commit == package_commit {
+                        return (*previous).clone();
                     }
+                }
 
-                    let content_name = content_path
-                        .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string();
-
-                    let content_path = format!("{}/{}", package_path, content_name);
-
-                    // hash the file using sha1
-                    let mut hasher = Sha1::new();
-                    let mut file = match std::fs::File::open(&content_path) {
-                        Ok(f) => f,
-                        Err(e) => {
-                            eprintln!("Error opening file {}: {}", content_path, e);
-                            krait::exit!(1);
-                        }
-                    };
+                let contents = hash_dir_contents(dir, &path, &package_name, &manifest.repo, branch_name);
 
-                    match std::io::copy(&mut file, &mut hasher) {
-                        Ok(b) => b,
-                        Err(e) => {
-                            eprintln!("Error hashing file {}: {}", content_path, e);
-                            krait::exit!(1);
-                        }
-                    };
+                ManifestPackage {
+                    target,
+                    path,
+                    commit: package_commit.clone(),
+                    contents,
+                    git_url: String::new(),
+                }
+            };
 
-                    let hash_bytes = hasher.finalize();
+            let target_packages: Vec<ManifestPackage> = if !package_manifest.git_url.is_empty() {
+                // a git-sourced package (declared via `krait.pkg.git_url` in
+                // its own manifest.lua) is cloned and read straight from the
+                // working tree at install time (see pkg::get_package), so
+                // there's nothing under packages/<name>/ to hash into
+                // content entries - just record where to find it
+                vec![ManifestPackage {
+                    target: "any".to_string(),
+                    path: package_path.clone(),
+                    commit: package_commit.clone(),
+                    contents: Vec::new(),
+                    git_url: package_manifest.git_url.clone(),
+                }]
+            } else if target_dirs.is_empty() {
+                vec![build_package(
+                    "any".to_string(),
+                    &package_dir,
+                    package_path.clone(),
+                )]
+            } else {
+                target_dirs
+                    .into_iter()
+                    .map(|(target, dir)| {
+                        let target_path = format!("{}/{}", package_path, target);
+                        build_package(target, &dir, target_path)
+                    })
+                    .collect()
+            };
 
-                    let hash = format!("{:x}", hash_bytes);
+            let mut packages = manifest.packages.clone();
 
-                    // get the download url
-                    let download_url = format!(
-                        "https://raw.githubusercontent.com/{}/{}/{}",
-                        manifest.repo, branch_name, content_path
-                    );
+            packages
+                .entry(package_name.clone())
+                .or_insert_with(HashMap::new)
+                .insert(version.clone(), target_packages);
 
-                    // TODO: add support non-github repos
+            manifest.packages = packages;
+        }
 
-                    contents.push(ManifestPackageContent {
-                        name: content_name,
-                        path: content_path,
-                        sha1: hash,
-                        url: download_url,
-                    });
-                }
+        // write the manifest to the repo root as manifest.lua
+
+        let manifest_str = manifest.to_string();
+
+        match std::fs::write(&manifest_path, &manifest_str) {
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error writing manifest: {}", e);
+                krait::exit!(1);
             }
+        }
 
-            let package = ManifestPackage {
-                path: package_path,
-                commit: package_commit,
-                contents,
+        // sign the manifest if a keypair was provided via WIX_SIGNING_KEY, so a
+        // compromised raw.githubusercontent host can't silently swap contents
+        if let Ok(key_path) = std::env::var("WIX_SIGNING_KEY") {
+            let key_bytes = match std::fs::read(&key_path) {
+                Ok(b) => b,
+                Err(e) => {
+                    eprintln!("Error reading signing key {}: {}", key_path, e);
+                    krait::exit!(1);
+                }
             };
 
-            // get the version from the package manifest
-            let version = package_manifest.ver;
+            let keypair = match Keypair::from_bytes(&key_bytes) {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("Error parsing signing key {}: {}", key_path, e);
+                    krait::exit!(1);
+                }
+            };
 
-            let mut packages = manifest.packages.clone();
+            let signature = Manifest::sign(&manifest_str, &keypair);
+            let sig_path = manifest_path.with_extension("lua.sig");
 
-            if packages.contains_key(&package_name) {
-                // check if the version is already in the manifest
-                if packages[&package_name].contains_key(&version) {
-                    // append the package to the existing version
-                    packages
-                        .get_mut(&package_name)
-                        .unwrap()
-                        .get_mut(&version)
-                        .unwrap()
-                        .push(package);
-                } else {
-                    // add the version to the package
-                    packages
-                        .get_mut(&package_name)
-                        .unwrap()
-                        .insert(version.clone(), vec![package]);
+            match std::fs::write(&sig_path, signature.to_bytes()) {
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Error writing manifest signature: {}", e);
+                    krait::exit!(1);
                 }
-            } else {
-                // add the package to the manifest
-                let mut hashmap = HashMap::new();
-                hashmap.insert(version.clone(), vec![package]);
-
-                packages.insert(package_name.clone(), hashmap);
             }
+        }
+    }
 
-            manifest.packages = packages;
+    // sign the serialized manifest text with an ed25519 keypair, producing a
+    // detached signature to be written alongside manifest.lua as manifest.lua.sig
+    pub fn sign(manifest_str: &str, keypair: &Keypair) -> Signature {
+        keypair.sign(manifest_str.as_bytes())
+    }
+
+    // verify a detached signature over the serialized manifest text against a
+    // trusted public key; the client runs this before trusting any URL in the
+    // manifest
+    pub fn verify_signature(
+        manifest_str: &str,
+        signature: &Signature,
+        public_key: &PublicKey,
+    ) -> Result<(), String> {
+        public_key
+            .verify(manifest_str.as_bytes(), signature)
+            .map_err(|e| format!("manifest signature verification failed: {}", e))
+    }
+}
+
+fn is_target_name(name: &str) -> bool {
+    name == "any" || TARGET_TRIPLES.iter().any(|(_, _, t)| *t == name)
+}
+
+// hash every file directly under `dir` (non-recursively) and build the
+// ManifestPackageContent list for it, rooted at `path_prefix` for urls.
+// files are hashed in parallel with rayon since this is the dominant cost of
+// manifest regeneration on large monorepos; the resulting order is kept
+// deterministic (sorted by name) so re-running produces a stable diff.
+fn hash_dir_contents(
+    dir: &std::path::Path,
+    path_prefix: &str,
+    package_name: &str,
+    repo: &str,
+    branch_name: &str,
+) -> Vec<ManifestPackageContent> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error reading package contents: {}", e);
+            krait::exit!(1);
         }
+    };
 
-        // write the manifest to the repo root as manifest.lua
+    let mut content_names: Vec<String> = Vec::new();
 
-        let manifest_str = manifest.to_string();
+    for content in entries {
+        if let Ok(content) = content {
+            let content_path = content.path();
 
-        match std::fs::write(&manifest_path, manifest_str) {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Error writing manifest: {}", e);
+            if content_path.is_dir() {
+                eprintln!(
+                    "Error: Package {} contains a nested directory",
+                    package_name
+                );
+                eprintln!("Nested directories are not currently supported");
                 krait::exit!(1);
             }
+
+            content_names.push(
+                content_path
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            );
         }
     }
+
+    content_names.sort();
+
+    content_names
+        .par_iter()
+        .map(|content_name| {
+            let content_path = format!("{}/{}", path_prefix, content_name);
+
+            // hash the file with sha1 (git-blob correspondence) and
+            // sha256 (authoritative integrity digest) in one read pass
+            let mut sha1_hasher = Sha1::new();
+            let mut sha256_hasher = Sha256::new();
+            let mut file = match std::fs::File::open(&content_path) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("Error opening file {}: {}", content_path, e);
+                    krait::exit!(1);
+                }
+            };
+
+            match std::io::copy(&mut file, &mut DualHasher(&mut sha1_hasher, &mut sha256_hasher)) {
+                Ok(b) => b,
+                Err(e) => {
+                    eprintln!("Error hashing file {}: {}", content_path, e);
+                    krait::exit!(1);
+                }
+            };
+
+            let hash = format!("{:x}", sha1_hasher.finalize());
+            let sha256_digest = sha256_hasher.finalize();
+            let sha256_hash = format!("{:x}", sha256_digest);
+            let integrity = sri_string("sha256", &sha256_digest);
+
+            // get the download url
+            let download_url = format!(
+                "https://raw.githubusercontent.com/{}/{}/{}",
+                repo, branch_name, content_path
+            );
+
+            // TODO: add support non-github repos
+
+            ManifestPackageContent {
+                name: content_name.clone(),
+                path: content_path,
+                sha1: hash,
+                sha256: sha256_hash,
+                integrity,
+                url: download_url,
+            }
+        })
+        .collect()
+}
+
+// forwards bytes read through std::io::copy into two hashers at once so a
+// file only needs to be streamed through once to produce both digests
+struct DualHasher<'a>(&'a mut Sha1, &'a mut Sha256);
+
+impl<'a> std::io::Write for DualHasher<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        self.1.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 // implement Display for Manifest
@@ -416,11 +807,21 @@ impl std::fmt::Display for Manifest {
         lua_script.push_str(&format!("m.last_update = \"{}\"\n", manifest.last_update));
         lua_script.push_str("\n");
 
-        // write the packages
-        for (package_name, versions) in manifest.packages {
+        // write the packages, in sorted order so the emitted manifest.lua is
+        // stable across runs regardless of HashMap iteration order (needed
+        // for clean diffs when nothing actually changed)
+        let mut package_names: Vec<&String> = manifest.packages.keys().collect();
+        package_names.sort();
+
+        for package_name in package_names {
+            let versions = &manifest.packages[package_name];
             lua_script.push_str(&format!("m.packages[\"{}\"] = {}\n", package_name, "{"));
 
-            for (version, packages) in versions {
+            let mut version_names: Vec<&String> = versions.keys().collect();
+            version_names.sort();
+
+            for version in version_names {
+                let packages = &versions[version];
                 lua_script.push_str(&format!(
                     "m.packages[\"{}\"][\"{}\"] = {}\n",
                     package_name, version, "{"
@@ -432,17 +833,27 @@ impl std::fmt::Display for Manifest {
                         package_name, version, package.path, "{"
                     ));
 
+                    lua_script.push_str(&format!(
+                        "m.packages[\"{}\"][\"{}\"][\"{}\"][\"target\"] = \"{}\"\n",
+                        package_name, version, package.path, package.target
+                    ));
+
                     lua_script.push_str(&format!(
                         "m.packages[\"{}\"][\"{}\"][\"{}\"][\"commit\"] = \"{}\"\n",
                         package_name, version, package.path, package.commit
                     ));
 
+                    lua_script.push_str(&format!(
+                        "m.packages[\"{}\"][\"{}\"][\"{}\"][\"git_url\"] = \"{}\"\n",
+                        package_name, version, package.path, package.git_url
+                    ));
+
                     lua_script.push_str(&format!(
                         "m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"] = {}\n",
                         package_name, version, package.path, "{"
                     ));
 
-                    for content in package.contents {
+                    for content in &package.contents {
                         lua_script.push_str(&format!(
                             "m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"] = {}\n",
                             package_name, version, package.path, content.name, "{"
@@ -450,6 +861,8 @@ impl std::fmt::Display for Manifest {
 
                         lua_script.push_str(&format!("m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"][\"path\"] = \"{}\"\n", package_name, version, package.path, content.name, content.path));
                         lua_script.push_str(&format!("m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"][\"sha1\"] = \"{}\"\n", package_name, version, package.path, content.name, content.sha1));
+                        lua_script.push_str(&format!("m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"][\"sha256\"] = \"{}\"\n", package_name, version, package.path, content.name, content.sha256));
+                        lua_script.push_str(&format!("m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"][\"integrity\"] = \"{}\"\n", package_name, version, package.path, content.name, content.integrity));
                         lua_script.push_str(&format!("m.packages[\"{}\"][\"{}\"][\"{}\"][\"contents\"][\"{}\"][\"url\"] = \"{}\"\n", package_name, version, package.path, content.name, content.url));
 
                         lua_script.push_str(&format!(
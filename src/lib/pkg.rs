@@ -0,0 +1,506 @@
+use std::collections::{HashMap, HashSet};
+
+use mlua::{DeserializeOptions, Lua, LuaSerdeExt, Table};
+use pyo3::Python;
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate as krait;
+
+// NOTE: the upstream raw-file layout used here mirrors manifest::gen_manifest
+// (packages/<name>/... for noarch files, packages/<name>/<target>/... for
+// per-target builds); see that module's TODO about
+// non-github repos, which the same assumption inherits here
+//
+// bare "owner/repo", not a full URL: Manifest::fetch builds the
+// raw.githubusercontent.com url itself from this plus a branch name
+pub const PKG_REPO: &str = "m1ten/wix-pkgs";
+
+#[derive(SmartDefault, Deserialize, Serialize, Debug, Clone)]
+pub struct PkgInfo {
+    pub name: String,
+    pub ver: String,
+
+    // "name@version-constraint" entries, e.g. "openssl@^3.0"
+    pub dependencies: Vec<String>,
+
+    // when set, gen_manifest sources this package directly from a git repo
+    // (see ManifestPackage::git_url) instead of hashing files under
+    // packages/<name>/ into raw.githubusercontent content entries. absent
+    // from the vast majority of existing package manifest.lua files, so it
+    // has to default rather than fail deserialization
+    #[serde(default)]
+    pub git_url: String,
+}
+
+impl PkgInfo {
+    pub fn parse(s: String) -> Self {
+        let lua = Lua::new();
+        let globals = lua.globals();
+
+        let krait_table = lua.create_table().expect("Failed to create krait table");
+        let pkg_table = lua.create_table().expect("Failed to create pkg table");
+
+        krait_table
+            .set("pkg", pkg_table)
+            .expect("Failed to set pkg table");
+
+        globals
+            .set("krait", krait_table)
+            .expect("Failed to set krait table");
+
+        if let Err(e) = lua.load(&s).exec() {
+            eprintln!("Error parsing package manifest: {}", e);
+            krait::exit!(1);
+        }
+
+        let krait_table: Table = globals.get("krait").expect("failed to get krait table");
+        let pkg_table: Table = krait_table.get("pkg").expect("failed to get pkg table");
+
+        let options = DeserializeOptions::new()
+            .deny_unsupported_types(false)
+            .deny_recursive_tables(false);
+
+        match lua.from_value_with(mlua::Value::Table(pkg_table), options) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Error parsing package manifest: {}", e);
+                krait::exit!(1);
+            }
+        }
+    }
+
+    // parse a "name@version-constraint" dependency entry
+    pub fn parse_dependency(spec: &str) -> (String, String) {
+        match spec.split_once('@') {
+            Some((name, constraint)) => (name.to_string(), constraint.to_string()),
+            None => (spec.to_string(), "*".to_string()),
+        }
+    }
+}
+
+// bare "major.minor.patch", missing components default to 0 (e.g. "3" -> (3, 0, 0))
+fn parse_version(s: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = s.trim().splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().unwrap_or("0").parse().ok()?;
+    let patch = parts.next().unwrap_or("0").parse().ok()?;
+    Some((major, minor, patch))
+}
+
+// split a constraint like "^3.0" or ">=1.2.3" into its operator and bound;
+// bare versions (no operator) are treated as "="
+fn split_constraint(constraint: &str) -> (&str, &str) {
+    for op in ["^", "~", ">=", "<=", ">", "<", "="] {
+        if let Some(bound) = constraint.trim().strip_prefix(op) {
+            return (op, bound.trim());
+        }
+    }
+
+    ("=", constraint.trim())
+}
+
+// check `version` (as returned by a package's manifest.lua) against a
+// "name@version-constraint" dependency's constraint half. unparseable
+// versions/constraints fall back to an exact string match rather than
+// silently passing
+fn version_satisfies(version: &str, constraint: &str) -> bool {
+    if constraint.is_empty() || constraint == "*" {
+        return true;
+    }
+
+    let (op, bound) = split_constraint(constraint);
+
+    let (actual, wanted) = match (parse_version(version), parse_version(bound)) {
+        (Some(a), Some(w)) => (a, w),
+        _ => return version == bound,
+    };
+
+    match op {
+        "^" => actual.0 == wanted.0 && actual >= wanted,
+        "~" => actual.0 == wanted.0 && actual.1 == wanted.1 && actual >= wanted,
+        ">=" => actual >= wanted,
+        "<=" => actual <= wanted,
+        ">" => actual > wanted,
+        "<" => actual < wanted,
+        _ => actual == wanted,
+    }
+}
+
+pub struct Package;
+
+impl Package {
+    // fetch the manifest and look `name`@`version` up in it. callers that
+    // already hold a manifest (main() fetches it once up front) should use
+    // `get_package_with_manifest` instead so every dependency/subcommand
+    // doesn't trigger its own full fetch-plus-signature-verification
+    pub async fn get_package(
+        name: String,
+        version: String,
+        os: String,
+        arch: String,
+    ) -> Result<String, String> {
+        let manifest = krait::manifest::Manifest::fetch(PKG_REPO, "main").await?;
+
+        Package::get_package_with_manifest(&manifest, name, version, os, arch).await
+    }
+
+    pub async fn get_package_with_manifest(
+        manifest: &krait::manifest::Manifest,
+        name: String,
+        version: String,
+        os: String,
+        arch: String,
+    ) -> Result<String, String> {
+        // select the ManifestPackage entry matching the running host, falling
+        // back to an "any"/noarch build, instead of guessing a raw os-arch url
+        let package = match manifest.select_package(&name, &version, &os, &arch) {
+            Some(p) => p,
+            None => return Ok("404: Not Found".to_string()),
+        };
+
+        // git-sourced packages are cloned and checked out to the pinned
+        // commit, then loaded straight from the working tree instead of
+        // being flattened into raw.githubusercontent urls
+        if !package.git_url.is_empty() {
+            // clone_or_checkout makes blocking libgit2 network calls,
+            // same reason the CAS fetch below is wrapped in spawn_blocking
+            let clone_name = name.clone();
+            let git_url = package.git_url.clone();
+            let commit = package.commit.clone();
+
+            let dir = tokio::task::spawn_blocking(move || {
+                krait::git_source::clone_or_checkout(&clone_name, &git_url, &commit)
+            })
+            .await
+            .map_err(|e| format!("Error loading git package {}: {}", name, e))?
+            .map_err(|e| format!("Error loading git package {}: {}", name, e))?;
+
+            let script_path = dir.join(format!("{}.py", version));
+
+            return match std::fs::read_to_string(&script_path) {
+                Ok(s) => Ok(s),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Ok("404: Not Found".to_string())
+                }
+                Err(e) => Err(format!("Error reading {}: {}", name, e)),
+            };
+        }
+
+        let content = match package
+            .contents
+            .iter()
+            .find(|c| c.name == format!("{}.py", version))
+        {
+            Some(c) => c,
+            None => return Ok("404: Not Found".to_string()),
+        };
+
+        // go through the CAS instead of downloading straight into memory, so
+        // a content digest already fetched by an earlier install (or a
+        // different version/package sharing the same file) is hard-linked
+        // in rather than pulled over the network again
+        let cas =
+            krait::cache::Cas::open().map_err(|e| format!("Error opening cache: {}", e))?;
+        let dest = dirs::home_dir()
+            .expect("failed to get home dir")
+            .join("wix/cache/_download")
+            .join(format!("{}-{}.py", name, version));
+
+        let content = content.clone();
+        let dest_for_fetch = dest.clone();
+        let found = tokio::task::spawn_blocking(move || {
+            krait::cache::fetch_content(&cas, &content, &dest_for_fetch)
+        })
+        .await
+        .map_err(|e| format!("Error fetching package {}: {}", name, e))?
+        .map_err(|e| format!("Error fetching package {}: {}", name, e))?;
+
+        if !found {
+            return Ok("404: Not Found".to_string());
+        }
+
+        std::fs::read_to_string(&dest)
+            .map_err(|e| format!("package {} is not valid utf-8: {}", name, e))
+    }
+
+    async fn get_pkg_info(name: &str, version: &str) -> Result<PkgInfo, String> {
+        let url = format!(
+            "https://raw.githubusercontent.com/{}/main/packages/{}/manifest.lua",
+            PKG_REPO, name
+        );
+
+        let resp = reqwest::get(&url)
+            .await
+            .map_err(|e| format!("Error fetching manifest for {}: {}", name, e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("{}@{} not found in repository", name, version));
+        }
+
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| format!("Error reading manifest for {}: {}", name, e))?;
+
+        Ok(PkgInfo::parse(text))
+    }
+
+    // resolve `name`@`version` plus its transitive dependencies into an
+    // ordered install plan (dependencies before dependents), deduplicating
+    // diamonds and rejecting cycles
+    pub async fn resolve(name: String, version: String) -> Result<Vec<PkgInfo>, String> {
+        let mut nodes: HashMap<String, PkgInfo> = HashMap::new();
+
+        // every constraint any edge in the graph asked of a given dependency
+        // name (including the root request). a diamond where "b" wants
+        // "d@^2.0" and "c" wants "d@^1.0" only fetches "d" once - its entry
+        // in `nodes` is keyed by name alone - so both constraints have to be
+        // collected up front and checked against whichever version actually
+        // got resolved, not just the one edge that happened to trigger the fetch
+        let mut constraints: HashMap<String, Vec<String>> = HashMap::new();
+        constraints.entry(name.clone()).or_insert_with(Vec::new).push(version.clone());
+
+        let mut stack = vec![(name, version)];
+
+        // fetch the full transitive closure first so the topological sort
+        // below has every node's dependency list available
+        while let Some((name, version)) = stack.pop() {
+            if nodes.contains_key(&name) {
+                continue;
+            }
+
+            let info = Package::get_pkg_info(&name, &version).await?;
+
+            for dep in &info.dependencies {
+                let (dep_name, dep_constraint) = PkgInfo::parse_dependency(dep);
+                constraints
+                    .entry(dep_name.clone())
+                    .or_insert_with(Vec::new)
+                    .push(dep_constraint.clone());
+
+                if !nodes.contains_key(&dep_name) {
+                    stack.push((dep_name, dep_constraint));
+                }
+            }
+
+            nodes.insert(name, info);
+        }
+
+        // get_pkg_info always returns whatever version the source repo's
+        // manifest.lua currently declares; reject it outright rather than
+        // silently installing something that doesn't satisfy every edge
+        // that named it as a dependency
+        check_constraints(&nodes, &constraints)?;
+
+        topo_sort(nodes)
+    }
+}
+
+// verify every edge's constraint on a dependency name against the single
+// version actually fetched for it, so a diamond where two dependents name
+// conflicting constraints on the same package can't silently keep whichever
+// one happened to be resolved first
+fn check_constraints(
+    nodes: &HashMap<String, PkgInfo>,
+    constraints: &HashMap<String, Vec<String>>,
+) -> Result<(), String> {
+    for (name, info) in nodes {
+        for constraint in &constraints[name] {
+            if !version_satisfies(&info.ver, constraint) {
+                return Err(format!(
+                    "{} resolved to {}, which does not satisfy requested constraint {}",
+                    name, info.ver, constraint
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Kahn's algorithm over the dependency graph, returning dependencies before
+// the packages that depend on them
+fn topo_sort(nodes: HashMap<String, PkgInfo>) -> Result<Vec<PkgInfo>, String> {
+    let mut in_degree: HashMap<String, usize> = nodes.keys().map(|n| (n.clone(), 0)).collect();
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+
+    for (name, info) in &nodes {
+        for dep in &info.dependencies {
+            let (dep_name, _) = PkgInfo::parse_dependency(dep);
+
+            if !nodes.contains_key(&dep_name) {
+                continue;
+            }
+
+            *in_degree.get_mut(name).unwrap() += 1;
+            dependents.entry(dep_name).or_insert_with(Vec::new).push(name.clone());
+        }
+    }
+
+    let mut ready: Vec<String> = in_degree
+        .iter()
+        .filter(|(_, deg)| **deg == 0)
+        .map(|(n, _)| n.clone())
+        .collect();
+    ready.sort();
+
+    let mut plan = Vec::new();
+    let mut visited: HashSet<String> = HashSet::new();
+
+    while let Some(name) = ready.pop() {
+        if !visited.insert(name.clone()) {
+            continue;
+        }
+
+        plan.push(nodes[&name].clone());
+
+        if let Some(next) = dependents.get(&name) {
+            for dependent in next {
+                let deg = in_degree.get_mut(dependent).unwrap();
+                *deg -= 1;
+                if *deg == 0 {
+                    ready.push(dependent.clone());
+                }
+            }
+        }
+    }
+
+    if plan.len() != nodes.len() {
+        let unresolved: Vec<&String> = nodes.keys().filter(|n| !visited.contains(*n)).collect();
+        return Err(format!(
+            "dependency cycle detected among: {}",
+            unresolved
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, deps: &[&str]) -> PkgInfo {
+        PkgInfo {
+            name: name.to_string(),
+            ver: "1.0.0".to_string(),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            git_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn topo_sort_orders_dependencies_before_dependents() {
+        let mut nodes = HashMap::new();
+        nodes.insert("a".to_string(), node("a", &["b@*"]));
+        nodes.insert("b".to_string(), node("b", &["c@*"]));
+        nodes.insert("c".to_string(), node("c", &[]));
+
+        let plan = topo_sort(nodes).expect("acyclic graph resolves");
+        let order: Vec<&str> = plan.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(order, vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn topo_sort_dedupes_diamond_dependencies() {
+        // a depends on b and c, both of which depend on d
+        let mut nodes = HashMap::new();
+        nodes.insert("a".to_string(), node("a", &["b@*", "c@*"]));
+        nodes.insert("b".to_string(), node("b", &["d@*"]));
+        nodes.insert("c".to_string(), node("c", &["d@*"]));
+        nodes.insert("d".to_string(), node("d", &[]));
+
+        let plan = topo_sort(nodes).expect("diamond resolves");
+
+        assert_eq!(plan.len(), 4);
+        assert_eq!(plan.last().unwrap().name, "a");
+    }
+
+    #[test]
+    fn check_constraints_rejects_conflicting_diamond_requests() {
+        // b wants d@^2.0, c wants d@^1.0, but only one "d" is ever fetched
+        let mut nodes = HashMap::new();
+        nodes.insert("d".to_string(), node("d", &[]));
+        nodes.get_mut("d").unwrap().ver = "2.0.0".to_string();
+
+        let mut constraints: HashMap<String, Vec<String>> = HashMap::new();
+        constraints.insert("d".to_string(), vec!["^2.0".to_string(), "^1.0".to_string()]);
+
+        assert!(check_constraints(&nodes, &constraints).is_err());
+    }
+
+    #[test]
+    fn check_constraints_accepts_compatible_diamond_requests() {
+        let mut nodes = HashMap::new();
+        nodes.insert("d".to_string(), node("d", &[]));
+        nodes.get_mut("d").unwrap().ver = "1.5.0".to_string();
+
+        let mut constraints: HashMap<String, Vec<String>> = HashMap::new();
+        constraints.insert("d".to_string(), vec!["^1.0".to_string(), ">=1.2".to_string()]);
+
+        assert!(check_constraints(&nodes, &constraints).is_ok());
+    }
+
+    #[test]
+    fn topo_sort_rejects_cycles() {
+        let mut nodes = HashMap::new();
+        nodes.insert("a".to_string(), node("a", &["b@*"]));
+        nodes.insert("b".to_string(), node("b", &["a@*"]));
+
+        assert!(topo_sort(nodes).is_err());
+    }
+
+    #[test]
+    fn version_satisfies_checks_caret_and_tilde_ranges() {
+        assert!(version_satisfies("3.2.1", "^3.0"));
+        assert!(!version_satisfies("4.0.0", "^3.0"));
+        assert!(version_satisfies("1.2.9", "~1.2.0"));
+        assert!(!version_satisfies("1.3.0", "~1.2.0"));
+        assert!(version_satisfies("2.0.0", "*"));
+        assert!(!version_satisfies("1.0.0", "2.0.0"));
+    }
+}
+
+impl Package {
+    pub fn install(package: String, name: String, path: String) {
+        if let Some(parent) = std::path::Path::new(&path).parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                eprintln!("Error creating cache directory for {}: {}", name, e);
+                krait::exit!(1);
+            }
+        }
+
+        if let Err(e) = std::fs::write(&path, &package) {
+            eprintln!("Error installing {}: {}", name, e);
+            krait::exit!(1);
+        }
+
+        // run the script's own install() through the embedded python
+        // interpreter - the same uniform entry point (see krait::call_py)
+        // a package script also uses to expose `version`/`dependencies()`
+        let result: pyo3::PyResult<()> =
+            Python::with_gil(|py| krait::call_py(py, &package, &path, &name, "install", vec![]));
+
+        if let Err(e) = result {
+            eprintln!("Error running install() for {}: {}", name, e);
+            krait::exit!(1);
+        }
+
+        println!("Installed {}.", name);
+    }
+
+    pub fn uninstall(_package: String, name: String, path: String) {
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("Error uninstalling {}: {}", name, e);
+            krait::exit!(1);
+        }
+
+        println!("Uninstalled {}.", name);
+    }
+}
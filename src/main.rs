@@ -82,7 +82,19 @@ async fn main() {
         path = path.replace("/", "\\");
     }
 
-    let package = pkg::Package::get_package(
+    // fetched once up front (and signature-verified if WIX_TRUSTED_KEY is
+    // set) and reused by every subcommand below instead of each one
+    // re-fetching and re-verifying it over the network
+    let manifest = match wix::manifest::Manifest::fetch(pkg::PKG_REPO, "main").await {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Error fetching manifest: {}", e);
+            exit!(1);
+        }
+    };
+
+    let package = pkg::Package::get_package_with_manifest(
+        &manifest,
         pkg_name.clone().to_lowercase(),
         pkg_version.clone(),
         os.clone(),
@@ -100,7 +112,68 @@ async fn main() {
                 );
                 exit!(1);
             }
-            _ => pkg::Package::install(package, pkg_name, path),
+            // `package` is already verified against the manifest's
+            // ManifestPackageContent::integrity by
+            // Package::get_package_with_manifest; a git-sourced package is
+            // instead read from its checkout at the pinned commit, unhashed
+            _ => {
+                let plan = match pkg::Package::resolve(pkg_name.clone(), pkg_version.clone()).await
+                {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Error resolving dependencies for {}: {}", pkg_name, e);
+                        exit!(1);
+                    }
+                };
+
+                for dep in &plan {
+                    if dep.name == pkg_name {
+                        // the requested package itself was already fetched above
+                        pkg::Package::install(package.clone(), pkg_name.clone(), path.clone());
+                        continue;
+                    }
+
+                    let dep_package = match pkg::Package::get_package_with_manifest(
+                        &manifest,
+                        dep.name.clone(),
+                        dep.ver.clone(),
+                        os.clone(),
+                        arch.clone(),
+                    )
+                    .await
+                    {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("Error fetching dependency {}: {}", dep.name, e);
+                            exit!(1);
+                        }
+                    };
+
+                    // built from the same "{name}/{os}-{arch}/{version}.py"
+                    // template as `path` above, not by substring-replacing
+                    // the already-rendered `path`: the requesting package's
+                    // name/version can otherwise collide with unrelated
+                    // segments (the os/arch string, a username in
+                    // `home_dir()`, another package's name containing this
+                    // one) and mangle the path
+                    let mut dep_path = dirs::home_dir()
+                        .unwrap()
+                        .join("wix/cache/{name}/{os}-{arch}/{version}.py")
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                        .replace("{name}", dep.name.as_str())
+                        .replace("{os}", os.as_str())
+                        .replace("{arch}", arch.as_str())
+                        .replace("{version}", dep.ver.as_str());
+
+                    if cfg!(windows) {
+                        dep_path = dep_path.replace("/", "\\");
+                    }
+
+                    pkg::Package::install(dep_package, dep.name.clone(), dep_path);
+                }
+            }
         },
         "uninstall" => match package.as_str() {
             "404: Not Found" => {
@@ -119,18 +192,106 @@ async fn main() {
                     "{} cloned to path '{}'.\nReview Script\n{}",
                     pkg_name, path, package
                 );
+
+                match pkg::Package::resolve(pkg_name.clone(), pkg_version.clone()).await {
+                    Ok(plan) => {
+                        println!("\nResolved install plan:");
+                        for (i, dep) in plan.iter().enumerate() {
+                            println!("  {}. {}@{}", i + 1, dep.name, dep.ver);
+                        }
+                    }
+                    Err(e) => eprintln!("Error resolving dependencies for {}: {}", pkg_name, e),
+                }
+
                 exit!(0);
             }
         },
         "update" => println!("Updating {}", pkg_name),
+        "verify" | "list-missing" => {
+            // reuse the manifest fetched once up front instead of paying
+            // for another full fetch (and signature check) here
+            let cache_root = dirs::home_dir().unwrap().join("wix/cache");
+            let list_missing_only = args.status.as_str() == "list-missing";
+            let report = manifest.verify(&cache_root, &os, &arch, list_missing_only);
+
+            if list_missing_only {
+                for path in &report.missing {
+                    println!("{}", path);
+                }
+                exit!(if report.missing.is_empty() { 0 } else { 1 });
+            }
+
+            println!("{} match, {} mismatch, {} missing", report.matches.len(), report.mismatches.len(), report.missing.len());
+
+            for (path, expected, actual) in &report.mismatches {
+                eprintln!("MISMATCH {}: expected {}, got {}", path, expected, actual);
+            }
+            for path in &report.missing {
+                eprintln!("MISSING {}", path);
+            }
+
+            exit!(if report.is_clean() { 0 } else { 1 });
+        },
+        "git-list" => match wix::git_source::list_cloned() {
+            Ok(repos) => {
+                if repos.is_empty() {
+                    println!("No git-backed packages cloned.");
+                } else {
+                    for repo in repos {
+                        println!("{} @ {} ({})", repo.name, repo.head, repo.path.display());
+                    }
+                }
+                exit!(0);
+            }
+            Err(e) => {
+                eprintln!("Error listing git-backed packages: {}", e);
+                exit!(1);
+            }
+        },
+        "git-update" => match wix::git_source::update_all() {
+            Ok(results) => {
+                for result in results {
+                    if result.advanced {
+                        println!("{}: {} -> {}", result.name, result.from, result.to);
+                    } else {
+                        println!("{}: up to date ({})", result.name, result.to);
+                    }
+                }
+                exit!(0);
+            }
+            Err(e) => {
+                eprintln!("Error updating git-backed packages: {}", e);
+                exit!(1);
+            }
+        },
         "clean" => {
             println!("Cleaning up.");
-            std::fs::remove_dir_all(dirs::home_dir().unwrap().join("wix/cache/"))
-                .unwrap_or_else(|err| {
-                    eprintln!("Error Cleaning Cache: {}", err);
+
+            // reuse the manifest fetched once up front (see above) instead
+            // of re-fetching and re-verifying it here
+            let referenced: std::collections::HashSet<String> = manifest
+                .packages
+                .values()
+                .flat_map(|versions| versions.values())
+                .flat_map(|packages| packages.iter())
+                .flat_map(|package| package.contents.iter())
+                .map(|content| content.integrity.clone())
+                .collect();
+
+            match wix::cache::Cas::open() {
+                Ok(cas) => match cas.gc(&referenced) {
+                    Ok(removed) => println!("Removed {} unreferenced cache entries.", removed),
+                    Err(e) => {
+                        eprintln!("Error cleaning cache: {}", e);
+                        exit!(1);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("Error opening cache: {}", e);
                     exit!(1);
-                });
-            
+                }
+            }
+
             println!("Cache Cleaned!");
             exit!(0);
         },